@@ -1,3 +1,5 @@
+use std::io;
+
 use bcrypt;
 use bcrypt::BcryptError;
 use thiserror;
@@ -8,6 +10,9 @@ pub enum HtpasswdError {
     #[error("Bcrypt error: {0}")]
     BCrypt(#[from] BcryptError),
 
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
     #[error("Password hashing error: {0}")]
     PwHash(#[from] pwhash::error::Error),
 
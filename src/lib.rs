@@ -21,11 +21,17 @@
 
 pub mod errors;
 
+mod apr1;
+mod sha1_base64;
+
 use std::borrow::Borrow;
 use std::io;
 use std::path::Path;
+use std::str::FromStr;
 use bcrypt::{DEFAULT_COST, Version};
+use hmac::{Hmac, Mac};
 use indexmap::IndexMap;
+use sha2::Sha256;
 
 use crate::errors::HtpasswdError;
 
@@ -41,6 +47,15 @@ use crate::errors::HtpasswdError;
 ///   * Sha-512
 ///       - supported by Nginx
 ///       - unsupported by htpasswd
+///   * Apache MD5 ($apr1$)
+///       - supported by htpasswd (its historical default)
+///       - unsupported by Nginx
+///   * {SHA} base64 SHA-1
+///       - supported by htpasswd (`htpasswd -s`)
+///       - unsupported by Nginx
+///   * NetBSD sha1-crypt ($sha1$)
+///       - unsupported by htpasswd, Nginx
+#[derive(Clone)]
 pub enum Algo {
     /// Use a specific cost. Must be within a range acceptable to bcrypt.
     Bcrypt {
@@ -63,19 +78,103 @@ pub enum Algo {
 
     /// Fastest, cheapest, and least secure. Useful for automated tests.
     Sha512MinRounds,
+
+    /// Apache's `$apr1$` md5crypt variant, htpasswd's historical default. The most portable
+    /// option where bcrypt or sha-512-crypt support is uncertain.
+    Md5Apr1,
+
+    /// Base64-encoded SHA-1, the `{SHA}` format written by `htpasswd -s`. Fast to compute and
+    /// correspondingly weak against offline attacks; present mainly for interoperability with
+    /// existing files.
+    Sha1Base64,
+
+    /// NetBSD's HMAC-SHA1-based `$sha1$` format. Must be within a range acceptable to
+    /// sha1-crypt; the scheme's own default is around 24680 rounds.
+    Sha1Crypt {
+        rounds: u32
+    },
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct Htpasswd {
     // Username and encrypted password
-    entries: IndexMap<String, String>
+    entries: IndexMap<String, String>,
+
+    // HMAC-SHA256 key applied to passwords before hashing/verifying, if configured via
+    // `with_pepper`.
+    pepper_key: Option<Vec<u8>>
+}
+
+impl std::fmt::Debug for Htpasswd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Htpasswd")
+            .field("entries", &self.entries)
+            .field("pepper_key", &self.pepper_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
 }
 
+// Prefixes an entry's stored hash when it was computed with a pepper applied, so that
+// `verify` knows to reapply the HMAC before dispatching to the underlying algorithm.
+const PEPPER_MARKER: &str = "*";
+
 impl Htpasswd {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Constructs an instance that applies an HMAC-SHA256 "pepper" to every password, using
+    /// `key` as a server-held secret, before hashing or verifying it with the usual algorithm.
+    /// This defends stored hashes against offline cracking if the htpasswd file leaks but the
+    /// key does not. To rotate to a new key over time, verify each entry against an instance
+    /// holding the old key, then switch that same instance to the new key (via
+    /// [`Htpasswd::set_pepper`]) and call [`Htpasswd::set_with`] to re-hash and save it. See
+    /// `set_pepper`'s docs for why [`Htpasswd::verify_and_upgrade`] can't drive that rotation.
+    pub fn with_pepper<K>(key: K) -> Self
+    where
+        K: AsRef<[u8]>
+    {
+        Self {
+            entries: IndexMap::new(),
+            pepper_key: Some(key.as_ref().to_vec())
+        }
+    }
+
+    /// Attaches (or replaces) the pepper key used to verify and hash with `self`, without
+    /// disturbing any entries already loaded. Use this to reload a peppered file written by
+    /// [`Htpasswd::with_pepper`] in a separate process: [`Htpasswd::read_from_path`] or
+    /// [`str::parse`] has no way to learn the key on its own, since it isn't stored in the
+    /// file, so it must be supplied again here.
+    ///
+    /// Also the mechanism for rotating to a new key, but rotation must be driven by hand
+    /// rather than through [`Htpasswd::verify_and_upgrade`]: call this with the old key and
+    /// verify the incoming password with it, *then* call this again with the new key and
+    /// re-hash with [`Htpasswd::set_with`]. `verify_and_upgrade` re-verifies using whatever
+    /// key is configured on `self` at the time it runs, so once the key has been switched to
+    /// the new one it fails to verify an entry still peppered with the old one, and never
+    /// reaches the rehash step.
+    pub fn set_pepper<K>(&mut self, key: K)
+    where
+        K: AsRef<[u8]>
+    {
+        self.pepper_key = Some(key.as_ref().to_vec());
+    }
+
+    // Applies the configured pepper to `password`, or returns it unchanged if none is configured.
+    fn apply_pepper(&self, password: &[u8]) -> Vec<u8> {
+        match &self.pepper_key {
+            Some(key) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key)
+                    .expect("HMAC-SHA256 accepts a key of any length");
+
+                mac.update(password);
+
+                mac.finalize().into_bytes().to_vec()
+            },
+            None => password.to_vec()
+        }
+    }
+
     pub fn set<U, P>(&mut self, username: U, password: P) -> Result<(), HtpasswdError>
     where
         U: Borrow<str>,
@@ -93,6 +192,8 @@ impl Htpasswd {
             Algo::Bcrypt { cost} => {
                 // Use bcrypt crate directly, as bcrypt passwords generated by pwhash
                 // don't validate by the htpasswd command line tool on Mac's.
+                let password = self.apply_pepper(password.as_ref());
+
                 bcrypt::hash_with_result(password, cost)?
                     .format_for_version(Version::TwoA)
             },
@@ -108,14 +209,39 @@ impl Htpasswd {
                     salt: None,  // Results in a random, max-length salt being used.
                 };
 
+                let password = self.apply_pepper(password.as_ref());
+
                 pwhash::sha512_crypt::hash_with(setup, password)?
             },
             Algo::Sha512Default => {
+                let password = self.apply_pepper(password.as_ref());
+
                 pwhash::sha512_crypt::hash(password)?
             },
             Algo::Sha512MinRounds => {
                 return self.set_with(Algo::Sha512 { rounds: pwhash::sha512_crypt::MIN_ROUNDS }, username, password)
             },
+            Algo::Md5Apr1 => {
+                apr1::hash(&self.apply_pepper(password.as_ref()))
+            },
+            Algo::Sha1Base64 => {
+                sha1_base64::hash_with_prefix(&self.apply_pepper(password.as_ref()))
+            },
+            Algo::Sha1Crypt { rounds } => {
+                let setup = pwhash::HashSetup {
+                    rounds: Some(rounds),
+                    salt: None,  // Results in a random salt being used.
+                };
+
+                let password = self.apply_pepper(password.as_ref());
+
+                pwhash::sha1_crypt::hash_with(setup, password)?
+            },
+        };
+
+        let encrypted = match &self.pepper_key {
+            Some(_) => format!("{PEPPER_MARKER}{encrypted}"),
+            None => encrypted
         };
 
         self.entries.insert(username.borrow().to_string(), encrypted);
@@ -123,20 +249,132 @@ impl Htpasswd {
         Ok(())
     }
 
-    // Private, because not all hash algorithms are implemented yet.
-    #[allow(dead_code)]  // Only used by tests
-    fn verify<U, P>(&self, username: U, password: P) -> bool
+    /// Verifies a password against the hash stored for `username`, recognizing the algorithm
+    /// from the stored hash's own prefix rather than requiring the caller to specify it. This
+    /// means it can verify entries this crate generated as well as ones read from a
+    /// pre-existing htpasswd file:
+    ///
+    ///   * `$2a$`, `$2y$`, `$2b$` - bcrypt
+    ///   * `$6$` - sha-512-crypt
+    ///   * `$apr1$` - Apache's md5crypt variant, htpasswd's historical default
+    ///   * `$sha1$` - NetBSD sha1-crypt
+    ///   * `{SHA}` - base64-encoded SHA-1, as written by `htpasswd -s`
+    ///
+    /// Entries created through an instance configured with [`Htpasswd::with_pepper`] are
+    /// tagged with a leading marker; verifying one requires `self` to be configured with the
+    /// same key (via `with_pepper` or [`Htpasswd::set_pepper`]), since the pepper is
+    /// re-applied to `password` before dispatching on the underlying hash format.
+    ///
+    /// Returns false if the username is not found, or if the stored hash's format is not
+    /// recognized.
+    pub fn verify<U, P>(&self, username: U, password: P) -> bool
     where
         U: Borrow<str>,
         P: Borrow<str> + AsRef<[u8]>
     {
-        if let Some(hashed) = self.entries.get(username.borrow()) {
-            pwhash::sha512_crypt::verify(password, hashed)
+        match self.entries.get(username.borrow()) {
+            Some(raw) => {
+                let (peppered, hashed) = match raw.strip_prefix(PEPPER_MARKER) {
+                    Some(rest) => (true, rest),
+                    None => (false, raw.as_str())
+                };
+
+                if peppered && self.pepper_key.is_none() {
+                    // Can't verify without the key that produced this entry
+                    return false;
+                }
+
+                let password = if peppered {
+                    self.apply_pepper(password.as_ref())
+                } else {
+                    password.as_ref().to_vec()
+                };
+                let password = password.as_slice();
+
+                if hashed.starts_with("$2a$") || hashed.starts_with("$2y$") || hashed.starts_with("$2b$") {
+                    bcrypt::verify(password, hashed)
+                        .unwrap_or(false)
+                }
+                else if hashed.starts_with("$6$") {
+                    pwhash::sha512_crypt::verify(password, hashed)
+                }
+                else if hashed.starts_with("$apr1$") {
+                    apr1::verify(password, hashed)
+                }
+                else if hashed.starts_with("$sha1$") {
+                    pwhash::sha1_crypt::verify(password, hashed)
+                }
+                else if let Some(digest) = hashed.strip_prefix("{SHA}") {
+                    sha1_base64::verify(password, digest)
+                }
+                else {
+                    // Unrecognized hash format
+                    false
+                }
+            },
+            None => {
+                // User not found
+                false
+            }
+        }
+    }
+
+    /// Reports whether the hash stored for `username` falls short of `target`: a different
+    /// algorithm than `target`, or the same algorithm with a weaker cost/round count. Lets
+    /// callers gradually strengthen a deployment's hashes via [`Htpasswd::verify_and_upgrade`]
+    /// as users authenticate, without forcing a password reset.
+    ///
+    /// Returns false if the username is not found, or if the stored hash's format is not
+    /// recognized.
+    pub fn needs_rehash<U>(&self, username: U, target: &Algo) -> bool
+    where
+        U: Borrow<str>
+    {
+        match self.entries.get(username.borrow()) {
+            Some(raw) => {
+                let hashed = raw.strip_prefix(PEPPER_MARKER).unwrap_or(raw);
+
+                match stored_params(hashed) {
+                    Some((family, cost)) => {
+                        let (target_family, target_cost) = target_params(target);
+
+                        if family != target_family {
+                            true
+                        } else {
+                            matches!((cost, target_cost), (Some(cost), Some(target_cost)) if cost < target_cost)
+                        }
+                    },
+                    None => {
+                        // Unrecognized hash format
+                        false
+                    }
+                }
+            },
+            None => {
+                // User not found
+                false
+            }
+        }
+    }
+
+    /// Verifies `password` for `username`, and if it matches and [`Htpasswd::needs_rehash`]
+    /// says the stored hash falls short of `target`, replaces the entry with a freshly
+    /// computed hash using `target`. Returns whether the password was correct; the upgrade,
+    /// when it happens, is incidental to the caller.
+    pub fn verify_and_upgrade<U, P>(&mut self, username: U, password: P, target: &Algo) -> Result<bool, HtpasswdError>
+    where
+        U: Borrow<str>,
+        P: Borrow<str> + AsRef<[u8]> + Clone
+    {
+        if !self.verify(username.borrow(), password.clone()) {
+            return Ok(false);
         }
-        else {
-            // User not found
-            false
+
+        if self.needs_rehash(username.borrow(), target) {
+            self.set_with(target.clone(), username, password)?;
         }
+
+        Ok(true)
     }
 
     pub fn write_to_path<P>(&self, path: P) -> Result<(), io::Error>
@@ -145,6 +383,100 @@ impl Htpasswd {
     {
         std::fs::write(path, self.to_string())
     }
+
+    /// Reads and parses an existing htpasswd file, such as one written by this crate or by the
+    /// `htpasswd` command line tool.
+    pub fn read_from_path<P>(path: P) -> Result<Self, HtpasswdError>
+    where
+        P: AsRef<Path>
+    {
+        std::fs::read_to_string(path)?
+            .parse()
+    }
+}
+
+#[derive(Eq, PartialEq)]
+enum Family {
+    Bcrypt,
+    Sha512,
+    Md5Apr1,
+    Sha1Base64,
+    Sha1Crypt,
+}
+
+/// Identifies the algorithm family of a stored hash, and its cost/round count if one can be
+/// read back out of it. Returns `None` if the hash's format is not recognized.
+fn stored_params(hashed: &str) -> Option<(Family, Option<u32>)> {
+    if hashed.starts_with("$2a$") || hashed.starts_with("$2y$") || hashed.starts_with("$2b$") {
+        let cost = hashed.split('$').nth(2)?.parse().ok();
+
+        Some((Family::Bcrypt, cost))
+    }
+    else if let Some(rest) = hashed.strip_prefix("$6$") {
+        let rounds = match rest.strip_prefix("rounds=") {
+            // "$6$rounds=N$salt$hash"
+            Some(rest) => rest.split('$').next().and_then(|r| r.parse().ok()),
+            // "$6$salt$hash" - no rounds segment means pwhash's default of 5000 was used
+            None => Some(pwhash::sha512_crypt::DEFAULT_ROUNDS),
+        };
+
+        Some((Family::Sha512, rounds))
+    }
+    else if hashed.starts_with("$apr1$") {
+        Some((Family::Md5Apr1, None))
+    }
+    else if let Some(rest) = hashed.strip_prefix("$sha1$") {
+        let rounds = rest.split('$').next().and_then(|r| r.parse().ok());
+
+        Some((Family::Sha1Crypt, rounds))
+    }
+    else if hashed.starts_with("{SHA}") {
+        Some((Family::Sha1Base64, None))
+    }
+    else {
+        None
+    }
+}
+
+/// The algorithm family a target [`Algo`] belongs to, and the cost/round count it implies,
+/// when one is known. `None` means "don't compare costs for this family" rather than "zero".
+fn target_params(algo: &Algo) -> (Family, Option<u32>) {
+    match algo {
+        Algo::Bcrypt { cost } => (Family::Bcrypt, Some(*cost)),
+        Algo::BCryptDefault => (Family::Bcrypt, Some(DEFAULT_COST)),
+        Algo::BcryptMinCost => (Family::Bcrypt, Some(pwhash::bcrypt::MIN_COST)),
+        Algo::Sha512 { rounds } => (Family::Sha512, Some(*rounds)),
+        Algo::Sha512Default => (Family::Sha512, Some(pwhash::sha512_crypt::DEFAULT_ROUNDS)),
+        Algo::Sha512MinRounds => (Family::Sha512, Some(pwhash::sha512_crypt::MIN_ROUNDS)),
+        Algo::Md5Apr1 => (Family::Md5Apr1, None),
+        Algo::Sha1Base64 => (Family::Sha1Base64, None),
+        Algo::Sha1Crypt { rounds } => (Family::Sha1Crypt, Some(*rounds)),
+    }
+}
+
+impl FromStr for Htpasswd {
+    type Err = HtpasswdError;
+
+    /// Parses the contents of an htpasswd file: one `user:hash` entry per line, in the order
+    /// encountered. Blank lines, lines starting with `#`, and any other line without a `:`
+    /// separator are treated as comments and skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = IndexMap::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some((username, hash)) = line.split_once(':') {
+                entries.insert(username.to_string(), hash.to_string());
+            }
+        }
+
+        Ok(Self { entries, pepper_key: None })
+    }
 }
 
 impl ToString for Htpasswd {
@@ -171,7 +503,7 @@ impl ToString for Htpasswd {
 mod test_verifies_against_apache_cli {
     use std::process::Command;
     use tempfile::tempdir;
-    use crate::Algo::{BcryptMinCost, Sha512Default, Sha512MinRounds};
+    use crate::Algo::{BcryptMinCost, Md5Apr1, Sha1Base64, Sha1Crypt, Sha512Default, Sha512MinRounds};
     use crate::Htpasswd;
 
     fn check(file: &str, username: &str, password: &str) {
@@ -231,6 +563,52 @@ mod test_verifies_against_apache_cli {
         check(&htpasswd_file, "qux", "baz");
     }
 
+    #[test]
+    fn verifies_md5_apr1_against_cli() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Md5Apr1, "a", "b")
+            .unwrap();
+
+        let tmp = tempdir()
+            .unwrap();
+
+        let htpasswd_file = tmp
+            .path()
+            .join("passwords")
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        htpasswd.write_to_path(&htpasswd_file)
+            .unwrap();
+
+        check(&htpasswd_file, "a", "b");
+    }
+
+    #[test]
+    fn verifies_sha1_base64_against_cli() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Sha1Base64, "a", "b")
+            .unwrap();
+
+        let tmp = tempdir()
+            .unwrap();
+
+        let htpasswd_file = tmp
+            .path()
+            .join("passwords")
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        htpasswd.write_to_path(&htpasswd_file)
+            .unwrap();
+
+        check(&htpasswd_file, "a", "b");
+    }
+
     #[test]
     fn verifies_sha512_default_against_self() {
         let mut htpasswd = Htpasswd::new();
@@ -258,4 +636,252 @@ mod test_verifies_against_apache_cli {
         // Incorrect password is rejected
         assert!(!htpasswd.verify("a", "c"));
     }
+
+    #[test]
+    fn verifies_sha1_crypt_against_self() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Sha1Crypt { rounds: 24680 }, "a", "b")
+            .unwrap();
+
+        // Correct password is verified
+        assert!(htpasswd.verify("a", "b"));
+
+        // Incorrect password is rejected
+        assert!(!htpasswd.verify("a", "c"));
+    }
+}
+
+#[cfg(test)]
+mod test_parses_existing_file {
+    use tempfile::tempdir;
+    use crate::Htpasswd;
+
+    #[test]
+    fn skips_comments_blanks_and_malformed_lines() {
+        let apr1_hash = crate::apr1::hash_with_salt(b"hunter2", "abcdefgh");
+        let sha1_hash = format!("{{SHA}}{}", crate::sha1_base64::hash(b"hunter2"));
+
+        let text = format!("\n# comment\nalice:{apr1_hash}\nnot a valid line\nbob:{sha1_hash}\n");
+
+        let htpasswd: Htpasswd = text.parse()
+            .unwrap();
+
+        assert!(htpasswd.verify("alice", "hunter2"));
+        assert!(!htpasswd.verify("alice", "wrong"));
+        assert!(htpasswd.verify("bob", "hunter2"));
+        assert!(!htpasswd.verify("nobody", "hunter2"));
+    }
+
+    #[test]
+    fn reads_from_path() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set("alice", "hunter2")
+            .unwrap();
+
+        let tmp = tempdir()
+            .unwrap();
+
+        let htpasswd_file = tmp
+            .path()
+            .join("passwords");
+
+        htpasswd.write_to_path(&htpasswd_file)
+            .unwrap();
+
+        let reread = Htpasswd::read_from_path(&htpasswd_file)
+            .unwrap();
+
+        assert!(reread.verify("alice", "hunter2"));
+    }
+}
+
+#[cfg(test)]
+mod test_rehashes_weak_entries {
+    use crate::Algo::{Bcrypt, Md5Apr1, Sha512, Sha512MinRounds};
+    use crate::Htpasswd;
+
+    #[test]
+    fn flags_a_weaker_cost_as_needing_rehash() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Bcrypt { cost: 4 }, "a", "b")
+            .unwrap();
+
+        assert!(htpasswd.needs_rehash("a", &Bcrypt { cost: 10 }));
+        assert!(!htpasswd.needs_rehash("a", &Bcrypt { cost: 4 }));
+        assert!(!htpasswd.needs_rehash("a", &Bcrypt { cost: 2 }));
+    }
+
+    #[test]
+    fn flags_a_different_algorithm_as_needing_rehash() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Sha512MinRounds, "a", "b")
+            .unwrap();
+
+        assert!(htpasswd.needs_rehash("a", &Bcrypt { cost: 10 }));
+    }
+
+    #[test]
+    fn does_not_flag_an_unknown_user() {
+        let htpasswd = Htpasswd::new();
+
+        assert!(!htpasswd.needs_rehash("a", &Bcrypt { cost: 10 }));
+    }
+
+    #[test]
+    fn verify_and_upgrade_rejects_wrong_password_without_upgrading() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Sha512 { rounds: pwhash::sha512_crypt::MIN_ROUNDS }, "a", "b")
+            .unwrap();
+
+        assert!(!htpasswd.verify_and_upgrade("a", "wrong", &Bcrypt { cost: 10 }).unwrap());
+        assert!(htpasswd.needs_rehash("a", &Bcrypt { cost: 10 }));
+    }
+
+    #[test]
+    fn verify_and_upgrade_replaces_a_weak_entry_on_success() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(Sha512MinRounds, "a", "b")
+            .unwrap();
+
+        assert!(htpasswd.verify_and_upgrade("a", "b", &Md5Apr1).unwrap());
+
+        // Upgraded entry is no longer sha-512, so it verifies and no longer needs rehashing
+        assert!(htpasswd.verify("a", "b"));
+        assert!(!htpasswd.needs_rehash("a", &Md5Apr1));
+    }
+}
+
+#[cfg(test)]
+mod test_applies_a_pepper {
+    use tempfile::tempdir;
+    use crate::Algo::BcryptMinCost;
+    use crate::Htpasswd;
+
+    #[test]
+    fn verifies_a_peppered_entry_with_the_same_key() {
+        let mut htpasswd = Htpasswd::with_pepper("server secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        assert!(htpasswd.verify("a", "b"));
+        assert!(!htpasswd.verify("a", "c"));
+    }
+
+    #[test]
+    fn rejects_a_peppered_entry_with_a_different_key() {
+        let mut htpasswd = Htpasswd::with_pepper("server secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        // Share the same entry under test, but with a different key, so this actually
+        // exercises the HMAC comparison rather than just an empty-entries lookup miss.
+        let wrong_key = Htpasswd {
+            entries: htpasswd.entries.clone(),
+            pepper_key: Some(b"a different secret".to_vec())
+        };
+
+        assert!(!wrong_key.verify("a", "b"));
+    }
+
+    #[test]
+    fn rejects_a_peppered_entry_without_any_key_configured() {
+        let mut htpasswd = Htpasswd::with_pepper("server secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        // Losing track of the key, such as by re-parsing a saved file, leaves entries
+        // unverifiable; there is no way to recover the pepper from the file alone.
+        let reparsed: Htpasswd = htpasswd.to_string()
+            .parse()
+            .unwrap();
+
+        assert!(!reparsed.verify("a", "b"));
+    }
+
+    #[test]
+    fn set_pepper_reattaches_the_key_after_reloading_from_disk() {
+        let mut htpasswd = Htpasswd::with_pepper("server secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        let tmp = tempdir()
+            .unwrap();
+
+        let htpasswd_file = tmp
+            .path()
+            .join("passwords");
+
+        htpasswd.write_to_path(&htpasswd_file)
+            .unwrap();
+
+        // Simulates a separate process reloading the file and supplying the out-of-band key
+        let mut reloaded = Htpasswd::read_from_path(&htpasswd_file)
+            .unwrap();
+
+        assert!(!reloaded.verify("a", "b"));
+
+        reloaded.set_pepper("server secret");
+
+        assert!(reloaded.verify("a", "b"));
+        assert!(!reloaded.verify("a", "c"));
+    }
+
+    #[test]
+    fn rotates_from_an_old_key_to_a_new_one() {
+        let mut htpasswd = Htpasswd::with_pepper("old secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        // Rotation is driven by hand: verify with the old key first, then switch keys and
+        // re-hash. `verify_and_upgrade` can't do this in one call, since by the time it
+        // re-verifies, `self` would already be holding the new key.
+        assert!(htpasswd.verify("a", "b"));
+
+        htpasswd.set_pepper("new secret");
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        assert!(htpasswd.verify("a", "b"));
+
+        htpasswd.set_pepper("old secret");
+        assert!(!htpasswd.verify("a", "b"));
+    }
+
+    #[test]
+    fn tags_peppered_entries_in_the_serialized_file() {
+        let mut htpasswd = Htpasswd::with_pepper("server secret");
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        let written = htpasswd.to_string();
+        let (_, hash) = written.trim_end().split_once(':').unwrap();
+
+        assert!(hash.starts_with('*'));
+    }
+
+    #[test]
+    fn an_unpeppered_instance_verifies_entries_it_wrote() {
+        let mut htpasswd = Htpasswd::new();
+
+        htpasswd.set_with(BcryptMinCost, "a", "b")
+            .unwrap();
+
+        let written = htpasswd.to_string();
+        let (_, hash) = written.trim_end().split_once(':').unwrap();
+
+        assert!(!hash.starts_with('*'));
+        assert!(htpasswd.verify("a", "b"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,22 @@
+//! The legacy `{SHA}` + base64(SHA-1(password)) hash format written by `htpasswd -s`.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use sha1::{Digest, Sha1};
+use subtle::ConstantTimeEq;
+
+/// Hashes `password`, producing the base64 text that follows the `{SHA}` prefix.
+pub(crate) fn hash(password: &[u8]) -> String {
+    STANDARD.encode(Sha1::digest(password))
+}
+
+/// Hashes `password`, producing the full `{SHA}<base64>` encoding.
+pub(crate) fn hash_with_prefix(password: &[u8]) -> String {
+    format!("{{SHA}}{}", hash(password))
+}
+
+/// Verifies `password` against `digest`, the base64 text following the `{SHA}` prefix.
+/// Compares in constant time, like the pwhash-backed verifiers this dispatches alongside.
+pub(crate) fn verify(password: &[u8], digest: &str) -> bool {
+    hash(password).as_bytes().ct_eq(digest.as_bytes()).into()
+}